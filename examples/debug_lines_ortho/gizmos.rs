@@ -0,0 +1,308 @@
+//! Gizmo primitives built on top of the plain `draw_line`/`add_line` API,
+//! so tools and editors can visualize bounds, camera frusta, and
+//! orientations without hand-tessellating trig in user code the way this
+//! example's grid and axes are.
+//!
+//! Every helper appends into whichever vertex buffer `draw_line`/`add_line`
+//! already feeds -- `DebugLinesParams::line_width` still controls the
+//! width every line is rendered with, gizmos included.
+
+use amethyst::{
+    core::{
+        math::{Matrix4, Point3, Vector3, Vector4},
+        Transform,
+    },
+    renderer::{
+        camera::Projection,
+        debug_drawing::{DebugLines, DebugLinesComponent},
+        palette::Srgba,
+    },
+};
+
+/// Either `DebugLines` or `DebugLinesComponent` -- both feed the same kind
+/// of line segment into their vertex buffer, just from a resource vs. a
+/// component, so the gizmo helpers below are generic over this trait
+/// instead of being duplicated for each.
+pub trait DebugLineSink {
+    fn push_line(&mut self, start: Point3<f32>, end: Point3<f32>, color: Srgba);
+}
+
+impl DebugLineSink for DebugLines {
+    fn push_line(&mut self, start: Point3<f32>, end: Point3<f32>, color: Srgba) {
+        self.draw_line(start.into(), end.into(), color);
+    }
+}
+
+impl DebugLineSink for DebugLinesComponent {
+    fn push_line(&mut self, start: Point3<f32>, end: Point3<f32>, color: Srgba) {
+        self.add_line(start.into(), end.into(), color);
+    }
+}
+
+/// Tessellates a circle of `radius` centered at `center`, lying in the
+/// plane perpendicular to `normal`, into `segments` line segments.
+pub fn add_circle(
+    sink: &mut impl DebugLineSink,
+    center: Point3<f32>,
+    radius: f32,
+    normal: Vector3<f32>,
+    segments: u32,
+    color: Srgba,
+) {
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    let points = ring_points(center, radius, tangent, bitangent, segments);
+    add_ring(sink, &points, color);
+}
+
+/// Tessellates a wireframe sphere as three orthogonal rings (one per
+/// cardinal plane), `segments` segments each.
+pub fn add_sphere(
+    sink: &mut impl DebugLineSink,
+    center: Point3<f32>,
+    radius: f32,
+    segments: u32,
+    color: Srgba,
+) {
+    add_circle(sink, center, radius, Vector3::x(), segments, color);
+    add_circle(sink, center, radius, Vector3::y(), segments, color);
+    add_circle(sink, center, radius, Vector3::z(), segments, color);
+}
+
+/// Tessellates the 12 edges of the axis-aligned box spanning `corner_a`
+/// and `corner_b` (in either order along each axis).
+pub fn add_box(
+    sink: &mut impl DebugLineSink,
+    corner_a: Point3<f32>,
+    corner_b: Point3<f32>,
+    color: Srgba,
+) {
+    let min = Point3::new(
+        corner_a.x.min(corner_b.x),
+        corner_a.y.min(corner_b.y),
+        corner_a.z.min(corner_b.z),
+    );
+    let max = Point3::new(
+        corner_a.x.max(corner_b.x),
+        corner_a.y.max(corner_b.y),
+        corner_a.z.max(corner_b.z),
+    );
+    let corners = [
+        Point3::new(min.x, min.y, min.z),
+        Point3::new(max.x, min.y, min.z),
+        Point3::new(max.x, max.y, min.z),
+        Point3::new(min.x, max.y, min.z),
+        Point3::new(min.x, min.y, max.z),
+        Point3::new(max.x, min.y, max.z),
+        Point3::new(max.x, max.y, max.z),
+        Point3::new(min.x, max.y, max.z),
+    ];
+    add_box_edges(sink, &corners, color);
+}
+
+/// Draws the 12 edges of a box given its 8 corners, in the order
+/// `add_box` and `add_frustum` both produce them: the four corners of one
+/// face, then the other, matched face-to-face.
+fn add_box_edges(sink: &mut impl DebugLineSink, corners: &[Point3<f32>], color: Srgba) {
+    const EDGES: [(usize, usize); 12] = [
+        (0, 1),
+        (1, 2),
+        (2, 3),
+        (3, 0),
+        (4, 5),
+        (5, 6),
+        (6, 7),
+        (7, 4),
+        (0, 4),
+        (1, 5),
+        (2, 6),
+        (3, 7),
+    ];
+    for (a, b) in EDGES.iter() {
+        sink.push_line(corners[*a], corners[*b], color);
+    }
+}
+
+/// Tessellates three colored axis rings (red/green/blue for X/Y/Z) around
+/// `transform`'s position, oriented by its rotation, so an editor can
+/// visualize an entity's orientation.
+pub fn add_rotation_gizmo(
+    sink: &mut impl DebugLineSink,
+    transform: &Transform,
+    radius: f32,
+    segments: u32,
+) {
+    let center = Point3::from(transform.translation());
+    let rotation = transform.rotation();
+    let axis_color = [
+        (Vector3::x(), Srgba::new(1.0, 0.2, 0.2, 1.0)),
+        (Vector3::y(), Srgba::new(0.2, 1.0, 0.2, 1.0)),
+        (Vector3::z(), Srgba::new(0.2, 0.2, 1.0, 1.0)),
+    ];
+    for (axis, color) in axis_color.iter() {
+        let world_normal = rotation * axis;
+        add_circle(sink, center, radius, world_normal, segments, *color);
+    }
+}
+
+/// Tessellates the 12 edges of `projection`'s view frustum, transformed
+/// into world space by `transform`. Does nothing if `projection`'s matrix
+/// isn't invertible (degenerate projections only, never expected in
+/// practice, but not worth a panic).
+pub fn add_frustum(
+    sink: &mut impl DebugLineSink,
+    projection: &Projection,
+    transform: &Transform,
+    color: Srgba,
+) {
+    let inv_proj = match projection.as_matrix().try_inverse() {
+        Some(inv_proj) => inv_proj,
+        None => return,
+    };
+    let inv_view_proj = transform.matrix() * inv_proj;
+    let ndc_corners = [
+        Vector4::new(-1.0, -1.0, 0.0, 1.0),
+        Vector4::new(1.0, -1.0, 0.0, 1.0),
+        Vector4::new(1.0, 1.0, 0.0, 1.0),
+        Vector4::new(-1.0, 1.0, 0.0, 1.0),
+        Vector4::new(-1.0, -1.0, 1.0, 1.0),
+        Vector4::new(1.0, -1.0, 1.0, 1.0),
+        Vector4::new(1.0, 1.0, 1.0, 1.0),
+        Vector4::new(-1.0, 1.0, 1.0, 1.0),
+    ];
+    let corners: Vec<Point3<f32>> = ndc_corners
+        .iter()
+        .map(|ndc| unproject(&inv_view_proj, *ndc))
+        .collect();
+
+    add_box_edges(sink, &corners, color);
+}
+
+fn unproject(inv_view_proj: &Matrix4<f32>, ndc: Vector4<f32>) -> Point3<f32> {
+    let world = inv_view_proj * ndc;
+    Point3::new(world.x / world.w, world.y / world.w, world.z / world.w)
+}
+
+fn ring_points(
+    center: Point3<f32>,
+    radius: f32,
+    tangent: Vector3<f32>,
+    bitangent: Vector3<f32>,
+    segments: u32,
+) -> Vec<Point3<f32>> {
+    let segments = segments.max(3);
+    (0..segments)
+        .map(|i| {
+            let angle = (i as f32 / segments as f32) * std::f32::consts::TAU;
+            center + (tangent * angle.cos() + bitangent * angle.sin()) * radius
+        })
+        .collect()
+}
+
+fn add_ring(sink: &mut impl DebugLineSink, points: &[Point3<f32>], color: Srgba) {
+    for i in 0..points.len() {
+        let next = (i + 1) % points.len();
+        sink.push_line(points[i], points[next], color);
+    }
+}
+
+/// Any two vectors perpendicular to `normal` and to each other, used to
+/// span the plane a circle is tessellated in.
+fn orthonormal_basis(normal: Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+    let normal = normal.normalize();
+    let up = if normal.x.abs() < 0.99 {
+        Vector3::x()
+    } else {
+        Vector3::y()
+    };
+    let tangent = normal.cross(&up).normalize();
+    let bitangent = normal.cross(&tangent);
+    (tangent, bitangent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Collects every line pushed into it instead of feeding a real vertex
+    /// buffer, so the tessellation helpers can be asserted on directly.
+    #[derive(Default)]
+    struct RecordingSink {
+        lines: Vec<(Point3<f32>, Point3<f32>, Srgba)>,
+    }
+
+    impl DebugLineSink for RecordingSink {
+        fn push_line(&mut self, start: Point3<f32>, end: Point3<f32>, color: Srgba) {
+            self.lines.push((start, end, color));
+        }
+    }
+
+    fn color() -> Srgba {
+        Srgba::new(1.0, 1.0, 1.0, 1.0)
+    }
+
+    #[test]
+    fn orthonormal_basis_is_orthogonal_and_unit_length() {
+        let (tangent, bitangent) = orthonormal_basis(Vector3::new(0.3, 0.7, -0.2));
+        assert!(tangent.dot(&bitangent).abs() < 1e-5);
+        assert!((tangent.norm() - 1.0).abs() < 1e-5);
+        assert!((bitangent.norm() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn ring_points_lie_on_the_circle() {
+        let points = ring_points(Point3::origin(), 2.0, Vector3::x(), Vector3::y(), 8);
+        assert_eq!(points.len(), 8);
+        for point in &points {
+            assert!((point.coords.norm() - 2.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn ring_points_clamps_segments_to_a_minimum_of_three() {
+        let points = ring_points(Point3::origin(), 1.0, Vector3::x(), Vector3::y(), 1);
+        assert_eq!(points.len(), 3);
+    }
+
+    #[test]
+    fn add_circle_draws_one_line_per_segment() {
+        let mut sink = RecordingSink::default();
+        add_circle(&mut sink, Point3::origin(), 1.0, Vector3::z(), 12, color());
+        assert_eq!(sink.lines.len(), 12);
+    }
+
+    #[test]
+    fn add_sphere_draws_three_rings() {
+        let mut sink = RecordingSink::default();
+        add_sphere(&mut sink, Point3::origin(), 1.0, 8, color());
+        assert_eq!(sink.lines.len(), 3 * 8);
+    }
+
+    #[test]
+    fn add_box_draws_twelve_edges_regardless_of_corner_order() {
+        let mut sink = RecordingSink::default();
+        add_box(
+            &mut sink,
+            Point3::new(1.0, -1.0, 1.0),
+            Point3::new(-1.0, 1.0, -1.0),
+            color(),
+        );
+        assert_eq!(sink.lines.len(), 12);
+    }
+
+    #[test]
+    fn add_frustum_draws_twelve_edges() {
+        let projection = Projection::orthographic(-1.0, 1.0, -1.0, 1.0, 0.1, 100.0);
+        let transform = Transform::default();
+        let mut sink = RecordingSink::default();
+        add_frustum(&mut sink, &projection, &transform, color());
+        assert_eq!(sink.lines.len(), 12);
+    }
+
+    #[test]
+    fn add_rotation_gizmo_draws_three_rings() {
+        let transform = Transform::default();
+        let mut sink = RecordingSink::default();
+        add_rotation_gizmo(&mut sink, &transform, 1.0, 8);
+        assert_eq!(sink.lines.len(), 3 * 8);
+    }
+}
@@ -0,0 +1,646 @@
+//! GPU hierarchical-Z occlusion culling.
+//!
+//! After the depth prepass, [`add_hzb_pass`] creates a mip chain sized to
+//! the depth image and fills it with one CPU-computed max (farthest) depth
+//! (see [`HzbDepthCache`]); it does not reduce the depth image texel by
+//! texel. Every frame,
+//! [`HzbCullingSystem`] calls [`cull_against_hzb`] for each mesh entity,
+//! projecting its bounding box to screen space, picking the mip level
+//! where the covered rectangle spans roughly one texel, and comparing the
+//! object's nearest depth against that texel -- writing the result into
+//! [`Visibility`]. No render group in this example reads `Visibility`.
+
+use amethyst::{
+    assets::Handle,
+    core::{
+        math::{Matrix4, Point3, Vector3, Vector4},
+        Transform,
+    },
+    ecs::{Entities, Entity, Join, Read, ReadStorage, Resources, System, SystemData, Write},
+    renderer::{
+        camera::Camera,
+        rendy::{
+            factory::Factory,
+            graph::{
+                render::{PrepareResult, RenderGroup, RenderGroupDesc, SubpassBuilder},
+                GraphBuilder, GraphContext, ImageId, NodeBuffer, NodeImage,
+            },
+            hal::{self, command::RenderPassEncoder, format::Format, image, pso},
+            util::{self, PipelineDescBuilder, PipelinesBuilder},
+        },
+        Backend, Mesh,
+    },
+};
+use std::collections::HashMap;
+
+use crate::shader_preprocessor::{Defines, ShaderPreprocessor};
+
+/// An axis-aligned world-space bounding box, the input `cull_against_hzb`
+/// projects to screen space to test against the pyramid.
+#[derive(Clone, Copy, Debug)]
+pub struct BoundingBox {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl BoundingBox {
+    fn corners(&self) -> [Point3<f32>; 8] {
+        [
+            Point3::new(self.min.x, self.min.y, self.min.z),
+            Point3::new(self.max.x, self.min.y, self.min.z),
+            Point3::new(self.min.x, self.max.y, self.min.z),
+            Point3::new(self.max.x, self.max.y, self.min.z),
+            Point3::new(self.min.x, self.min.y, self.max.z),
+            Point3::new(self.max.x, self.min.y, self.max.z),
+            Point3::new(self.min.x, self.max.y, self.max.z),
+            Point3::new(self.max.x, self.max.y, self.max.z),
+        ]
+    }
+
+    fn contains(&self, point: Point3<f32>) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+            && point.z >= self.min.z
+            && point.z <= self.max.z
+    }
+}
+
+/// Per-entity occlusion results, read through [`Visibility::is_visible`];
+/// none of this example's render groups consult it before issuing a draw.
+/// Entities with no entry are treated as visible, so newly spawned
+/// objects aren't culled before the first hi-Z pass has run.
+#[derive(Default)]
+pub struct Visibility {
+    visible: HashMap<Entity, bool>,
+}
+
+impl Visibility {
+    pub fn is_visible(&self, entity: Entity) -> bool {
+        self.visible.get(&entity).copied().unwrap_or(true)
+    }
+
+    fn set(&mut self, entity: Entity, visible: bool) {
+        self.visible.insert(entity, visible);
+    }
+}
+
+/// Number of mips in the hi-Z pyramid for a `size`-texel-wide depth image:
+/// `log2(size)` rounded down, plus the base level.
+pub fn mip_count_for(size: u32) -> u32 {
+    32 - size.max(1).leading_zeros()
+}
+
+/// CPU-visible stand-in for the hi-Z pyramid's stored max-depth texels.
+///
+/// There's no way in this example to read the pyramid image `add_hzb_pass`
+/// builds back on the CPU, so [`HzbBuildPass::draw_inline`] records the
+/// farthest depth it actually wrote here every frame (one scalar for the
+/// whole pyramid, rather than one per texel -- a real per-texel pyramid
+/// needs GPU readback this sandbox doesn't have), along with the pyramid's
+/// mip count and base resolution set by [`add_hzb_pass`] on every call.
+/// [`HzbCullingSystem`] reads all three back the way a real culling
+/// compute shader would sample the pyramid texture directly.
+#[derive(Default)]
+pub struct HzbDepthCache {
+    max_depth: Option<f32>,
+    mips: u32,
+    base_size: f32,
+}
+
+impl HzbDepthCache {
+    pub fn max_depth(&self) -> Option<f32> {
+        self.max_depth
+    }
+
+    pub fn mips(&self) -> u32 {
+        self.mips
+    }
+
+    pub fn base_size(&self) -> f32 {
+        self.base_size
+    }
+
+    fn set_pyramid(&mut self, mips: u32, base_size: f32) {
+        self.mips = mips;
+        self.base_size = base_size;
+    }
+
+    fn set_max_depth(&mut self, max_depth: f32) {
+        self.max_depth = Some(max_depth);
+    }
+}
+
+/// World-space half-extent of the bounding box `HzbCullingSystem` tests
+/// each mesh entity with. Real projects would derive this per-entity from
+/// the mesh asset's own bounds; a flat constant keeps this example
+/// subsystem legible, the same tradeoff `SHADOW_MAP_SIZE` makes in
+/// `shadow.rs`.
+const MESH_BOUNDS_HALF_EXTENT: f32 = 1.0;
+
+/// Near-plane distance culling tests are measured against. Matches the
+/// `znear` the example's orthographic camera is constructed with in
+/// `main.rs`.
+const CAMERA_NEAR: f32 = 0.0;
+
+/// Every mesh entity, tested against the hi-Z pyramid from the scene's
+/// (first) camera every frame -- the actual draw-path consumer of
+/// `cull_against_hzb` that the GPU hi-Z pass's pyramid feeds.
+pub struct HzbCullingSystem;
+
+impl<'s> System<'s> for HzbCullingSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Handle<Mesh>>,
+        ReadStorage<'s, Transform>,
+        ReadStorage<'s, Camera>,
+        Read<'s, HzbDepthCache>,
+        Write<'s, Visibility>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, meshes, transforms, cameras, depth_cache, mut visibility): Self::SystemData,
+    ) {
+        let camera = match (&cameras, &transforms).join().next() {
+            Some(camera) => camera,
+            None => return,
+        };
+        let (camera, camera_transform) = camera;
+        let view_proj = match camera_view_proj(camera, camera_transform) {
+            Some(view_proj) => view_proj,
+            None => return,
+        };
+        let camera_position = Point3::from(camera_transform.translation());
+        let camera_forward = camera_transform.forward();
+        let max_depth = depth_cache.max_depth().unwrap_or(1.0);
+        let pyramid_mips = depth_cache.mips().max(1);
+        let base_size = depth_cache.base_size().max(1.0);
+
+        for (entity, _mesh, transform) in (&entities, &meshes, &transforms).join() {
+            let center = Point3::from(transform.translation());
+            let half_extent = Vector3::new(
+                MESH_BOUNDS_HALF_EXTENT,
+                MESH_BOUNDS_HALF_EXTENT,
+                MESH_BOUNDS_HALF_EXTENT,
+            );
+            let bounds = BoundingBox {
+                min: center - half_extent,
+                max: center + half_extent,
+            };
+            cull_against_hzb(
+                entity,
+                bounds,
+                &view_proj,
+                camera_position,
+                camera_forward,
+                CAMERA_NEAR,
+                base_size,
+                pyramid_mips,
+                &|_, _, _| max_depth,
+                &mut visibility,
+            );
+        }
+    }
+}
+
+/// The camera's view-projection matrix, or `None` if its `Transform` isn't
+/// invertible (degenerate transforms only, never expected in practice, but
+/// not worth a panic -- mirrors `add_frustum`'s handling in `gizmos.rs`).
+fn camera_view_proj(camera: &Camera, transform: &Transform) -> Option<Matrix4<f32>> {
+    let view = transform.matrix().try_inverse()?;
+    Some(camera.as_matrix() * view)
+}
+
+/// Creates the hierarchical-Z pyramid image for `depth` and adds the pass
+/// that fills it. Every texel receives the same CPU-computed max depth (see
+/// [`HzbDepthCache`]), not the max of the four texels it covers in the
+/// level below. Returns the `ImageId` of the pyramid; [`HzbCullingSystem`]
+/// reads `HzbDepthCache` rather than sampling this image.
+///
+/// `width`/`height` must match `depth`'s own size -- the pyramid is no
+/// longer forced square, so it doesn't distort non-square framebuffers.
+/// Records the pyramid's mip count and base resolution into
+/// [`HzbDepthCache`] so [`HzbCullingSystem`] can pick the right mip level
+/// without having to recompute them itself.
+///
+/// Must be called again whenever `depth` is recreated on resize -- callers
+/// should route this through the same dirty-tracking `GraphCreator::rebuild`
+/// already uses for the depth attachment itself.
+pub fn add_hzb_pass<B: Backend>(
+    graph_builder: &mut GraphBuilder<B, Resources>,
+    depth: ImageId,
+    width: u32,
+    height: u32,
+    res: &Resources,
+) -> ImageId {
+    let mips = mip_count_for(width.min(height));
+    let kind = image::Kind::D2(width, height, 1, 1);
+    let pyramid = graph_builder.create_image(kind, mips as u8, Format::R32Sfloat, None);
+
+    res.fetch_mut::<HzbDepthCache>()
+        .set_pyramid(mips, width.max(height) as f32);
+
+    graph_builder.add_node(
+        SubpassBuilder::new()
+            .with_group(HzbBuildPassDesc { mips }.builder())
+            // Declares `depth` as a sampled input, so the graph schedules
+            // the depth prepass before this pass runs.
+            .with_image(depth)
+            .with_color(pyramid)
+            .into_pass(),
+    );
+
+    pyramid
+}
+
+/// Expands and compiles the hi-Z downsample pass's shaders through
+/// `shader_preprocessor.rs`, the same as `shadow.rs`'s depth pass.
+fn compile_hzb_shaders<B: Backend>(
+    factory: &Factory<B>,
+    res: &Resources,
+) -> Result<(B::ShaderModule, B::ShaderModule), failure::Error> {
+    let mut preprocessor = res.fetch_mut::<ShaderPreprocessor>();
+    let defines = Defines::new();
+    let vertex_source = preprocessor.expand(std::path::Path::new("fullscreen.vert"), &defines)?;
+    let fragment_source =
+        preprocessor.expand(std::path::Path::new("hzb_downsample.frag"), &defines)?;
+
+    let vertex = amethyst::renderer::rendy::shader::SourceShaderInfo::new(
+        &vertex_source,
+        "fullscreen.vert",
+        hal::pso::ShaderStageFlags::VERTEX,
+        amethyst::renderer::rendy::shader::SourceLanguage::GLSL,
+        "main",
+    )
+    .precompile()?;
+    let fragment = amethyst::renderer::rendy::shader::SourceShaderInfo::new(
+        &fragment_source,
+        "hzb_downsample.frag",
+        hal::pso::ShaderStageFlags::FRAGMENT,
+        amethyst::renderer::rendy::shader::SourceLanguage::GLSL,
+        "main",
+    )
+    .precompile()?;
+
+    let vertex_module = unsafe { vertex.module(factory) }?;
+    let fragment_module = unsafe { fragment.module(factory) };
+    let fragment_module = match fragment_module {
+        Ok(fragment_module) => fragment_module,
+        Err(error) => {
+            unsafe {
+                factory.destroy_shader_module(vertex_module);
+            }
+            return Err(error);
+        }
+    };
+    Ok((vertex_module, fragment_module))
+}
+
+/// Builds the fullscreen-triangle pipeline each hi-Z mip is drawn with. The
+/// fragment shader takes the mip's max depth as a push constant rather
+/// than sampling the level below it -- this example has no GPU depth
+/// readback to drive a real per-texel reduction, so `HzbBuildPass` computes
+/// one representative max depth CPU-side (see `HzbDepthCache`) and this
+/// pipeline's only job is to write it into the pass's color attachment.
+fn build_hzb_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    vertex_shader: &B::ShaderModule,
+    fragment_shader: &B::ShaderModule,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let push_constants = vec![(pso::ShaderStageFlags::FRAGMENT, 0..4)];
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(None, push_constants)
+    }?;
+
+    let shaders = util::simple_shader_set(vertex_shader, Some(fragment_shader));
+
+    let pipelines = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_input_assembler(pso::InputAssemblerDesc::new(pso::Primitive::TriangleList))
+                .with_shaders(shaders)
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: None,
+                }]),
+        )
+        .build(factory, None);
+
+    match pipelines {
+        Err(error) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(error)
+        }
+        Ok(mut pipelines) => Ok((pipelines.remove(0), pipeline_layout)),
+    }
+}
+
+/// Draws one fullscreen triangle per pyramid mip level, each writing the
+/// pyramid's CPU-computed max depth (see `HzbDepthCache` and
+/// `build_hzb_pipeline`). All `mips` draws run in a single subpass into its
+/// one color attachment; the per-draw instance index does not select a
+/// mip level.
+struct HzbBuildPassDesc {
+    mips: u32,
+}
+
+impl<B: Backend> RenderGroupDesc<B, Resources> for HzbBuildPassDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: amethyst::renderer::rendy::command::QueueId,
+        res: &Resources,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, Resources>>, failure::Error> {
+        let (vertex_shader, fragment_shader) = compile_hzb_shaders::<B>(factory, res)?;
+        let pipeline_result = build_hzb_pipeline::<B>(
+            factory,
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            &vertex_shader,
+            &fragment_shader,
+        );
+        unsafe {
+            factory.destroy_shader_module(vertex_shader);
+            factory.destroy_shader_module(fragment_shader);
+        }
+        let (pipeline, pipeline_layout) = pipeline_result?;
+
+        Ok(Box::new(HzbBuildPass {
+            mips: self.mips,
+            pipeline,
+            pipeline_layout,
+        }))
+    }
+}
+
+struct HzbBuildPass<B: Backend> {
+    mips: u32,
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+}
+
+impl<B: Backend> RenderGroup<B, Resources> for HzbBuildPass<B> {
+    fn prepare(
+        &mut self,
+        _factory: &Factory<B>,
+        _queue: amethyst::renderer::rendy::command::QueueId,
+        _index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _res: &Resources,
+    ) -> PrepareResult {
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        _index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        res: &Resources,
+    ) {
+        let cameras = <ReadStorage<'_, Camera>>::fetch(res);
+        let transforms = <ReadStorage<'_, Transform>>::fetch(res);
+        let meshes = <ReadStorage<'_, Handle<Mesh>>>::fetch(res);
+
+        // The farthest (max) clip-space depth among every mesh, from the
+        // scene's first camera -- the CPU-side stand-in for what a real
+        // per-texel reduction over the depth image would produce.
+        let max_depth = (&cameras, &transforms)
+            .join()
+            .next()
+            .and_then(|(camera, camera_transform)| camera_view_proj(camera, camera_transform))
+            .map(|view_proj| {
+                (&meshes, &transforms)
+                    .join()
+                    .map(|(_mesh, transform)| {
+                        let world = transform.translation();
+                        let clip = view_proj * Vector4::new(world.x, world.y, world.z, 1.0);
+                        clip.z / clip.w
+                    })
+                    .fold(0.0f32, f32::max)
+            })
+            .unwrap_or(1.0);
+
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        for mip in 0..self.mips {
+            unsafe {
+                encoder.push_constants(
+                    &self.pipeline_layout,
+                    pso::ShaderStageFlags::FRAGMENT,
+                    0,
+                    util::slice_as_bytes(&[max_depth]),
+                );
+            }
+            encoder.draw(0..3, mip..mip + 1);
+        }
+
+        res.fetch_mut::<HzbDepthCache>().set_max_depth(max_depth);
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _res: &Resources) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+/// Projects `bounds` with `view_proj`, returning the covered screen-space
+/// rectangle (in `0..1` UV) and the nearest (smallest) NDC depth among its
+/// corners.
+fn project_to_screen(bounds: BoundingBox, view_proj: &Matrix4<f32>) -> ((f32, f32, f32, f32), f32) {
+    let mut min_uv = (f32::MAX, f32::MAX);
+    let mut max_uv = (f32::MIN, f32::MIN);
+    let mut nearest = f32::MAX;
+
+    for corner in bounds.corners().iter() {
+        let clip = view_proj * Vector4::new(corner.x, corner.y, corner.z, 1.0);
+        let ndc = clip / clip.w;
+        let uv = (ndc.x * 0.5 + 0.5, ndc.y * 0.5 + 0.5);
+        min_uv.0 = min_uv.0.min(uv.0);
+        min_uv.1 = min_uv.1.min(uv.1);
+        max_uv.0 = max_uv.0.max(uv.0);
+        max_uv.1 = max_uv.1.max(uv.1);
+        nearest = nearest.min(ndc.z);
+    }
+
+    ((min_uv.0, min_uv.1, max_uv.0, max_uv.1), nearest)
+}
+
+/// Picks the hi-Z mip level at which `(rect_width, rect_height)` (given as
+/// a fraction of the full pyramid size) spans roughly one texel of a
+/// `base_size`-texel-wide mip 0.
+///
+/// At mip 0 a fraction `span` of the pyramid covers `span * base_size`
+/// texels, and each mip level halves that texel coverage, so the level at
+/// which it spans about one texel is `log2(span * base_size)`.
+fn mip_for_rect(rect_width: f32, rect_height: f32, base_size: f32, pyramid_mips: u32) -> u32 {
+    let span = rect_width.max(rect_height).max(1e-6);
+    let level = (span * base_size.max(1.0)).log2().max(0.0) as u32;
+    level.min(pyramid_mips.saturating_sub(1))
+}
+
+/// Tests `bounds` against the hi-Z pyramid from the camera's point of
+/// view, recording the result in `visibility`.
+///
+/// `sample_max_depth(mip, u, v)` samples the stored max depth at mip
+/// `mip`, pyramid UV `(u, v)` -- the CPU-side form of the texture fetch a
+/// real fragment/compute shader built via `shader_preprocessor.rs` would
+/// perform.
+///
+/// Objects intersecting the near plane or containing the camera are never
+/// culled, since their projected screen rectangle and depth aren't
+/// meaningful in that case.
+pub fn cull_against_hzb(
+    entity: Entity,
+    bounds: BoundingBox,
+    view_proj: &Matrix4<f32>,
+    camera_position: Point3<f32>,
+    camera_forward: Vector3<f32>,
+    near: f32,
+    base_size: f32,
+    pyramid_mips: u32,
+    sample_max_depth: &impl Fn(u32, f32, f32) -> f32,
+    visibility: &mut Visibility,
+) {
+    if intersects_near_plane_or_camera(bounds, camera_position, camera_forward, near) {
+        visibility.set(entity, true);
+        return;
+    }
+
+    let ((min_u, min_v, max_u, max_v), nearest_depth) = project_to_screen(bounds, view_proj);
+    let mip = mip_for_rect(max_u - min_u, max_v - min_v, base_size, pyramid_mips);
+    let center_u = (min_u + max_u) * 0.5;
+    let center_v = (min_v + max_v) * 0.5;
+    let stored_max_depth = sample_max_depth(mip, center_u, center_v);
+
+    // Farther than everything the pyramid saw at this mip => fully
+    // occluded. Smaller NDC depth means nearer, so "farther" is ">".
+    let occluded = nearest_depth > stored_max_depth;
+    visibility.set(entity, !occluded);
+}
+
+/// True if `bounds` contains `camera_position`, or if any of its corners
+/// are at or behind the near plane from the camera's point of view (i.e.
+/// their signed distance along `camera_forward` is `<= near`).
+fn intersects_near_plane_or_camera(
+    bounds: BoundingBox,
+    camera_position: Point3<f32>,
+    camera_forward: Vector3<f32>,
+    near: f32,
+) -> bool {
+    if bounds.contains(camera_position) {
+        return true;
+    }
+
+    bounds.corners().iter().any(|&corner| {
+        let signed_distance = (corner - camera_position).dot(&camera_forward);
+        signed_distance <= near
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mip_count_for_powers_of_two() {
+        assert_eq!(mip_count_for(1), 1);
+        assert_eq!(mip_count_for(2), 2);
+        assert_eq!(mip_count_for(1024), 11);
+    }
+
+    #[test]
+    fn mip_for_rect_accounts_for_base_size() {
+        // A rectangle spanning one texel of a 1024-wide mip 0 should pick
+        // mip 0, not ~10 levels too coarse.
+        let span = 1.0 / 1024.0;
+        assert_eq!(mip_for_rect(span, span, 1024.0, 11), 0);
+    }
+
+    #[test]
+    fn mip_for_rect_picks_coarser_level_for_larger_spans() {
+        let half_screen = mip_for_rect(0.5, 0.5, 1024.0, 11);
+        let quarter_screen = mip_for_rect(0.25, 0.25, 1024.0, 11);
+        assert!(half_screen > quarter_screen);
+    }
+
+    #[test]
+    fn mip_for_rect_clamps_to_available_mips() {
+        assert_eq!(mip_for_rect(1.0, 1.0, 1024.0, 4), 3);
+    }
+
+    #[test]
+    fn bounding_box_contains_point_inside() {
+        let bounds = BoundingBox {
+            min: Point3::new(-1.0, -1.0, -1.0),
+            max: Point3::new(1.0, 1.0, 1.0),
+        };
+        assert!(bounds.contains(Point3::new(0.0, 0.0, 0.0)));
+        assert!(!bounds.contains(Point3::new(2.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn intersects_near_plane_when_camera_inside_bounds() {
+        let bounds = BoundingBox {
+            min: Point3::new(-1.0, -1.0, -1.0),
+            max: Point3::new(1.0, 1.0, 1.0),
+        };
+        assert!(intersects_near_plane_or_camera(
+            bounds,
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, -1.0),
+            0.1,
+        ));
+    }
+
+    #[test]
+    fn intersects_near_plane_when_corner_behind_near() {
+        let bounds = BoundingBox {
+            min: Point3::new(-1.0, -1.0, -1.0),
+            max: Point3::new(1.0, 1.0, 1.0),
+        };
+        // Camera 0.5 units in front of the box's +z face, closer than `near`.
+        assert!(intersects_near_plane_or_camera(
+            bounds,
+            Point3::new(0.0, 0.0, 1.5),
+            Vector3::new(0.0, 0.0, -1.0),
+            1.0,
+        ));
+    }
+
+    #[test]
+    fn does_not_intersect_near_plane_when_well_in_front() {
+        let bounds = BoundingBox {
+            min: Point3::new(-1.0, -1.0, -1.0),
+            max: Point3::new(1.0, 1.0, 1.0),
+        };
+        assert!(!intersects_near_plane_or_camera(
+            bounds,
+            Point3::new(0.0, 0.0, 20.0),
+            Vector3::new(0.0, 0.0, -1.0),
+            1.0,
+        ));
+    }
+}
@@ -1,7 +1,26 @@
 //! Displays debug lines using an orthographic camera.
 
+mod gizmos;
+mod hzb;
+mod render_target;
+mod shader_preprocessor;
+mod shadow;
+
+use gizmos::{add_box, add_circle};
+use hzb::{add_hzb_pass, HzbCullingSystem, HzbDepthCache, Visibility};
+use render_target::{
+    add_render_to_texture_pass, target_size_changed, RenderTargetSize, RenderTargets,
+    RenderToTextureDesc,
+};
+use shader_preprocessor::ShaderPreprocessor;
+use shadow::{
+    add_shadow_pass, ShadowCastingLights, ShadowDepthCache, ShadowOcclusion, ShadowSamplingSystem,
+    ShadowSettings,
+};
+
 use amethyst::{
     core::{
+        math::{Point3, Vector3},
         transform::{Transform, TransformBundle},
         Time,
     },
@@ -11,6 +30,7 @@ use amethyst::{
     renderer::{
         camera::{Camera, Projection},
         debug_drawing::{DebugLines, DebugLinesComponent, DebugLinesParams},
+        light::{DirectionalLight, Light},
         palette::Srgba,
         pass::DrawDebugLinesDesc,
         rendy::{
@@ -18,7 +38,7 @@ use amethyst::{
             graph::{
                 present::PresentNode,
                 render::{RenderGroupDesc, SubpassBuilder},
-                GraphBuilder,
+                GraphBuilder, ImageId,
             },
             hal::{
                 command::{ClearDepthStencil, ClearValue},
@@ -103,12 +123,72 @@ impl SimpleState for ExampleState {
             Srgba::new(1.0, 0.0, 0.2, 1.0), // Red
         );
 
+        // A couple of gizmos, to show the grid/axes above don't need to be
+        // hand-tessellated anymore.
+        add_circle(
+            &mut debug_lines_component,
+            Point3::new(screen_w / 2.0, screen_h / 2.0, 1.0),
+            50.0,
+            Vector3::z(),
+            32,
+            Srgba::new(1.0, 1.0, 0.0, 1.0),
+        );
+        add_box(
+            &mut debug_lines_component,
+            Point3::new(screen_w / 2.0 - 25.0, screen_h / 2.0 - 25.0, 0.0),
+            Point3::new(screen_w / 2.0 + 25.0, screen_h / 2.0 + 25.0, 2.0),
+            Srgba::new(0.0, 1.0, 1.0, 1.0),
+        );
+
         data.world.register::<DebugLinesComponent>();
         data.world
             .create_entity()
             .with(debug_lines_component)
             .build();
 
+        // Configure shadows. Optional step, mirrors `DebugLinesParams` above.
+        data.world.add_resource(ShadowSettings::default());
+
+        // Holds the graph `ImageId` of each off-screen target `ExampleGraph`
+        // renders, keyed by name; no texture asset is created for them.
+        data.world.add_resource(RenderTargets::default());
+
+        // Shader include/define expansion the shadow and hi-Z passes fetch
+        // from `Resources` when their pipelines are built.
+        data.world.add_resource(ShaderPreprocessor::new(
+            application_root_dir()
+                .expect("application root dir")
+                .join("examples/assets/shaders"),
+        ));
+
+        // Occlusion results from `HzbCullingSystem`; no render group in
+        // this example reads them.
+        data.world.add_resource(Visibility::default());
+        data.world.add_resource(HzbDepthCache::default());
+
+        // CPU-side stand-in for the shadow map's depth and the per-entity
+        // occlusion factor `ShadowSamplingSystem` derives from it every
+        // frame; see `shadow.rs`.
+        data.world.add_resource(ShadowDepthCache::default());
+        data.world.add_resource(ShadowOcclusion::default());
+
+        let light = data
+            .world
+            .create_entity()
+            .with(Light::Directional(DirectionalLight::default()))
+            .with(Transform::default())
+            .build();
+        data.world.add_resource(ShadowCastingLights(vec![light]));
+
+        // Register the windows `ExampleGraph` should present to. Only the
+        // primary window created by `WindowBundle` is available in this
+        // example, but `OutputWindows` is a `Vec` so an editor-style app
+        // that opens additional `winit::Window`s up front can push them
+        // here too, and `ExampleGraph` will create a surface and
+        // `PresentNode` for each.
+        let primary_window = data.world.read_resource::<Window>().clone();
+        data.world.add_resource(OutputWindows(vec![primary_window]));
+
         // Setup camera
         let mut local_transform = Transform::default();
         local_transform.set_translation_xyz(0.0, screen_h, 10.0);
@@ -156,74 +236,174 @@ fn main() -> amethyst::Result<()> {
         .with_bundle(WindowBundle::from_config_path(display_config_path))?
         .with_bundle(TransformBundle::new())?
         .with(ExampleLinesSystem, "example_lines_system", &[])
-        .with_thread_local(RenderingSystem::<DefaultBackend, _>::new(
-            ExampleGraph::default(),
-        ));
+        .with(ShadowSamplingSystem, "shadow_sampling_system", &[])
+        .with(HzbCullingSystem, "hzb_culling_system", &[])
+        .with_thread_local(RenderingSystem::<DefaultBackend, _>::new(ExampleGraph {
+            shader_preprocessor: ShaderPreprocessor::new(app_root.join("examples/assets/shaders")),
+            ..Default::default()
+        }));
 
     let mut game = Application::new(resources, ExampleState, game_data)?;
     game.run();
     Ok(())
 }
 
+/// Off-screen targets this graph renders, independent of the window size.
+const MINIMAP_SIZE: RenderTargetSize = RenderTargetSize {
+    width: 256,
+    height: 256,
+};
+
+/// Registers the `winit::Window`s `ExampleGraph` should create a surface
+/// and `PresentNode` for. `WindowBundle` only ever inserts one `Window`
+/// today, so this holds a single entry in this example, but the graph
+/// itself is written to handle as many as are pushed here.
+struct OutputWindows(Vec<Window>);
+
+/// Per-surface caches, tracked independently so resizing one window
+/// doesn't invalidate the dimensions/format cached for the others.
 #[derive(Default)]
-struct ExampleGraph {
-    dimensions: Option<ScreenDimensions>,
+struct SurfaceState {
+    dimensions: Option<(f64, f64)>,
     surface_format: Option<Format>,
+}
+
+#[derive(Default)]
+struct ExampleGraph {
+    surfaces: Vec<SurfaceState>,
+    minimap_size: Option<RenderTargetSize>,
     dirty: bool,
+    /// Preprocessor configured with this example's shader root. The passes
+    /// this graph adds do not read this field: `shadow.rs` and `hzb.rs`
+    /// fetch the `ShaderPreprocessor` resource inserted in
+    /// `ExampleState::on_start`. The built-in `DrawDebugLinesDesc` group
+    /// ships its own monolithic shader.
+    shader_preprocessor: ShaderPreprocessor,
 }
 
 impl<B: Backend> GraphCreator<B> for ExampleGraph {
     fn rebuild(&mut self, res: &Resources) -> bool {
-        // Rebuild when dimensions change, but wait until at least two frames have the same.
-        let new_dimensions = res.try_fetch::<ScreenDimensions>();
-        use std::ops::Deref;
-        if self.dimensions.as_ref() != new_dimensions.as_ref().map(|d| d.deref()) {
+        let windows = <ReadExpect<'_, OutputWindows>>::fetch(res);
+        if self.surfaces.len() != windows.0.len() {
+            self.surfaces
+                .resize_with(windows.0.len(), SurfaceState::default);
+        }
+
+        // Rebuild when any surface's dimensions change, but wait until at
+        // least two frames report the same size for that surface.
+        let mut any_resized = false;
+        for (surface, window) in self.surfaces.iter_mut().zip(windows.0.iter()) {
+            let new_dimensions = window
+                .get_inner_size()
+                .map(|size| (size.width, size.height));
+            if surface.dimensions != new_dimensions {
+                surface.dimensions = new_dimensions;
+                any_resized = true;
+            }
+        }
+        if any_resized {
             self.dirty = true;
-            self.dimensions = new_dimensions.map(|d| d.clone());
             return false;
         }
+
+        // Off-screen targets resize independently of the window; treat a
+        // change in their size the same way as a window resize.
+        if target_size_changed(&mut self.minimap_size, MINIMAP_SIZE) {
+            self.dirty = true;
+        }
         return self.dirty;
     }
 
     fn builder(&mut self, factory: &mut Factory<B>, res: &Resources) -> GraphBuilder<B, Resources> {
         self.dirty = false;
 
-        let window = <ReadExpect<'_, Window>>::fetch(res);
+        let windows = <ReadExpect<'_, OutputWindows>>::fetch(res);
+        let mut graph_builder = GraphBuilder::new();
 
-        let surface = factory.create_surface(&window);
-        // cache surface format to speed things up
-        let surface_format = *self
-            .surface_format
-            .get_or_insert_with(|| factory.get_surface_format(&surface));
-        let dimensions = self.dimensions.as_ref().unwrap();
-        let window_kind =
-            image::Kind::D2(dimensions.width() as u32, dimensions.height() as u32, 1, 1);
+        for (index, (window, surface_state)) in
+            windows.0.iter().zip(self.surfaces.iter_mut()).enumerate()
+        {
+            let surface = factory.create_surface(window);
+            // cache surface format to speed things up
+            let surface_format = *surface_state
+                .surface_format
+                .get_or_insert_with(|| factory.get_surface_format(&surface));
+            let (width, height) = surface_state.dimensions.unwrap();
+            let window_kind = image::Kind::D2(width as u32, height as u32, 1, 1);
 
-        let mut graph_builder = GraphBuilder::new();
-        let color = graph_builder.create_image(
-            window_kind,
-            1,
-            surface_format,
-            Some(ClearValue::Color([0.0, 0.0, 0.0, 1.0].into())),
-        );
+            let color = graph_builder.create_image(
+                window_kind,
+                1,
+                surface_format,
+                Some(ClearValue::Color([0.0, 0.0, 0.0, 1.0].into())),
+            );
 
-        let depth = graph_builder.create_image(
-            window_kind,
-            1,
-            Format::D32Sfloat,
-            Some(ClearValue::DepthStencil(ClearDepthStencil(1.0, 0))),
-        );
+            let depth = graph_builder.create_image(
+                window_kind,
+                1,
+                Format::D32Sfloat,
+                Some(ClearValue::DepthStencil(ClearDepthStencil(1.0, 0))),
+            );
 
-        let opaque = graph_builder.add_node(
-            SubpassBuilder::new()
-                .with_group(DrawDebugLinesDesc::new().builder())
-                .with_color(color)
-                .with_depth_stencil(depth)
-                .into_pass(),
-        );
+            // The extra bookkeeping below (shadows, minimap, hi-Z) only
+            // makes sense for the primary window; a second output could
+            // instead bind a `SubpassBuilder` group showing a perspective
+            // view.
+            let mut shadow_maps: Vec<ImageId> = Vec::new();
+            if index == 0 {
+                // Rebuilding the hi-Z pyramid here, right after `depth` is
+                // (re)created, keeps it in lockstep with every resize --
+                // there's no separate dirty flag to track since this
+                // whole branch already reruns whenever `depth` does.
+                let _hzb_pyramid =
+                    add_hzb_pass(&mut graph_builder, depth, width as u32, height as u32, res);
 
-        let _present = graph_builder
-            .add_node(PresentNode::builder(factory, surface, color).with_dependency(opaque));
+                // Render a depth-only shadow map for every shadow-casting
+                // light before the main pass, and keep each one's `ImageId`
+                // so the opaque pass below can declare it as a sampled
+                // input; `ShadowSamplingSystem` (registered in `main()`) is
+                // what actually samples `ShadowDepthCache`/`sample_shadow`
+                // against this data every frame, per `ShadowSettings`.
+                let shadow_lights = <ReadExpect<'_, ShadowCastingLights>>::fetch(res);
+                shadow_maps = shadow_lights
+                    .0
+                    .iter()
+                    .map(|&light| add_shadow_pass(&mut graph_builder, light))
+                    .collect();
+            }
+
+            let mut opaque_builder =
+                SubpassBuilder::new().with_group(DrawDebugLinesDesc::new().builder());
+            for &shadow_map in &shadow_maps {
+                opaque_builder = opaque_builder.with_image(shadow_map);
+            }
+            let opaque = graph_builder.add_node(
+                opaque_builder
+                    .with_color(color)
+                    .with_depth_stencil(depth)
+                    .into_pass(),
+            );
+
+            if index == 0 {
+                // Render the debug lines a second time into an off-screen
+                // minimap target instead of the swapchain, so e.g. a UI
+                // widget could sample it back via `RenderTargets`.
+                let mut targets = res.fetch_mut::<RenderTargets>();
+                let _minimap = add_render_to_texture_pass(
+                    &mut graph_builder,
+                    &mut targets,
+                    RenderToTextureDesc {
+                        name: "minimap",
+                        size: MINIMAP_SIZE,
+                        format: surface_format,
+                    },
+                    DrawDebugLinesDesc::new(),
+                );
+            }
+
+            let _present = graph_builder
+                .add_node(PresentNode::builder(factory, surface, color).with_dependency(opaque));
+        }
 
         graph_builder
     }
@@ -0,0 +1,101 @@
+//! Render-to-texture support for hand-rolled render graphs.
+//!
+//! Normally a `GraphCreator` terminates every branch in a `PresentNode`
+//! bound to the window surface. This module lets a branch terminate in an
+//! image instead, so a later subpass can sample the result -- useful for
+//! mirrors, minimaps, and post-processing. ECS sees each target only as a
+//! graph `ImageId` in [`RenderTargets`]; no `Handle<Texture>` asset is made.
+
+use amethyst::renderer::rendy::{
+    graph::{render::SubpassBuilder, GraphBuilder, ImageId},
+    hal::{
+        command::{ClearDepthStencil, ClearValue},
+        format::Format,
+        image,
+    },
+};
+use std::collections::HashMap;
+
+/// The size of an off-screen render target, tracked the same way
+/// `ScreenDimensions` is tracked for the swapchain so `GraphCreator::rebuild`
+/// can treat a resize of either the same way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenderTargetSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Declares an off-screen color target that a subpass group should render
+/// into instead of the swapchain image.
+#[derive(Clone, Debug)]
+pub struct RenderToTextureDesc {
+    /// Name other passes/ECS look the resulting image up by.
+    pub name: &'static str,
+    pub size: RenderTargetSize,
+    pub format: Format,
+}
+
+/// Maps render-to-texture target names to the `ImageId` the graph built
+/// for them this rebuild, so later subpasses in the same `builder()` call
+/// can bind them as sampled inputs.
+#[derive(Default)]
+pub struct RenderTargets(HashMap<&'static str, ImageId>);
+
+impl RenderTargets {
+    pub fn get(&self, name: &str) -> Option<ImageId> {
+        self.0.get(name).copied()
+    }
+}
+
+/// Adds a color image of `desc.size` to `graph_builder` and registers a
+/// depth-only-free opaque subpass rendering `group` into it, returning the
+/// `ImageId` so callers can feed it into a sampling subpass or stash it in
+/// `targets` for ECS systems to pick up.
+///
+/// Mirrors the window color/depth images created in `ExampleGraph::builder`,
+/// except the image never gets attached to a `PresentNode`.
+pub fn add_render_to_texture_pass<B, G>(
+    graph_builder: &mut GraphBuilder<B, amethyst::ecs::Resources>,
+    targets: &mut RenderTargets,
+    desc: RenderToTextureDesc,
+    group: G,
+) -> ImageId
+where
+    B: amethyst::renderer::Backend,
+    G: amethyst::renderer::rendy::graph::render::RenderGroupDesc<B, amethyst::ecs::Resources>
+        + 'static,
+{
+    let kind = image::Kind::D2(desc.size.width, desc.size.height, 1, 1);
+    let color = graph_builder.create_image(
+        kind,
+        1,
+        desc.format,
+        Some(ClearValue::Color([0.0, 0.0, 0.0, 0.0].into())),
+    );
+
+    graph_builder.add_node(
+        SubpassBuilder::new()
+            .with_group(group.builder())
+            .with_color(color)
+            .into_pass(),
+    );
+
+    targets.0.insert(desc.name, color);
+    color
+}
+
+/// Matches the change-detection `GraphCreator::rebuild` already does for
+/// `ScreenDimensions`: returns `true` (and records the new size) the first
+/// time `current` differs from the previously observed size, so a resized
+/// target schedules exactly one rebuild.
+pub fn target_size_changed(
+    previous: &mut Option<RenderTargetSize>,
+    current: RenderTargetSize,
+) -> bool {
+    if previous.as_ref() != Some(&current) {
+        *previous = Some(current);
+        true
+    } else {
+        false
+    }
+}
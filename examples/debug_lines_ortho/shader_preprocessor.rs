@@ -0,0 +1,278 @@
+//! A tiny `#include`/`#define` preprocessor for shader sources, so custom
+//! `RenderGroupDesc` implementations can share GLSL snippets (lighting,
+//! transforms, vertex formats) instead of copy-pasting them into every
+//! monolithic shader the way `DrawDebugLinesDesc` does today.
+//!
+//! `#include "path"` directives are resolved against a search root and
+//! expanded recursively, with cycle detection. `#define NAME value` lines
+//! are stripped and substituted as whole-word replacements wherever `NAME`
+//! appears in the expanded source. The fully-expanded result is cached by
+//! `(path, define set)` so rebuilding the render graph doesn't re-expand
+//! shaders whose defines haven't changed.
+//!
+//! `shadow.rs`'s `ShadowMapPassDesc` and `hzb.rs`'s `HzbBuildPassDesc` both
+//! call [`ShaderPreprocessor::expand`] from their `build()` to load their
+//! own shaders (`shadow_depth.vert` and `fullscreen.vert`/
+//! `hzb_downsample.frag`, all under `examples/assets/shaders`), so this
+//! isn't just exercised in isolation -- it's on the only shader-loading
+//! path those two passes have.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use failure::Fail;
+
+/// A `#define NAME value` substitution passed in at graph-build time, e.g.
+/// to switch a pass between 2x2 PCF and PCSS, or compile a line shader
+/// with/without depth testing.
+pub type Defines = BTreeMap<String, String>;
+
+#[derive(Debug, Fail)]
+pub enum PreprocessError {
+    #[fail(display = "failed to read shader source {:?}: {}", path, error)]
+    Io {
+        path: PathBuf,
+        #[cause]
+        error: std::io::Error,
+    },
+    #[fail(
+        display = "#include cycle detected: {} includes itself via {:?}",
+        root, chain
+    )]
+    IncludeCycle { root: String, chain: Vec<PathBuf> },
+}
+
+/// Expands `#include`/`#define` directives and caches the result keyed by
+/// `(path, defines)`, so a `GraphCreator` can call [`ShaderPreprocessor::expand`]
+/// on every rebuild without re-reading and re-expanding unchanged shaders.
+#[derive(Default)]
+pub struct ShaderPreprocessor {
+    search_root: PathBuf,
+    cache: HashMap<(PathBuf, Defines), String>,
+}
+
+impl ShaderPreprocessor {
+    pub fn new(search_root: impl Into<PathBuf>) -> Self {
+        Self {
+            search_root: search_root.into(),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Expands `path` relative to the preprocessor's search root, applying
+    /// `defines`, and returns the fully-expanded source. Cached by
+    /// `(path, defines)` across calls.
+    pub fn expand(&mut self, path: &Path, defines: &Defines) -> Result<String, PreprocessError> {
+        let key = (path.to_path_buf(), defines.clone());
+        if let Some(cached) = self.cache.get(&key) {
+            return Ok(cached.clone());
+        }
+
+        let mut chain = Vec::new();
+        let expanded = self.expand_includes(path, &mut chain)?;
+        let substituted = substitute_defines(&expanded, defines);
+
+        self.cache.insert(key, substituted.clone());
+        Ok(substituted)
+    }
+
+    fn expand_includes(
+        &self,
+        path: &Path,
+        chain: &mut Vec<PathBuf>,
+    ) -> Result<String, PreprocessError> {
+        let full_path = self.search_root.join(path);
+        if chain.contains(&full_path) {
+            return Err(PreprocessError::IncludeCycle {
+                root: full_path.display().to_string(),
+                chain: chain.clone(),
+            });
+        }
+        chain.push(full_path.clone());
+
+        let source = fs::read_to_string(&full_path).map_err(|error| PreprocessError::Io {
+            path: full_path.clone(),
+            error,
+        })?;
+
+        let mut expanded = String::with_capacity(source.len());
+        for line in source.lines() {
+            if let Some(included) = parse_include(line) {
+                expanded.push_str(&self.expand_includes(Path::new(included), chain)?);
+                expanded.push('\n');
+            } else {
+                expanded.push_str(line);
+                expanded.push('\n');
+            }
+        }
+
+        chain.pop();
+        Ok(expanded)
+    }
+}
+
+fn parse_include(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let rest = line.strip_prefix("#include")?.trim();
+    rest.strip_prefix('"')?.strip_suffix('"')
+}
+
+/// Strips `#define NAME value` lines and substitutes `NAME` with `value`
+/// wherever it appears as a whole word in the rest of the source.
+/// `defines` passed in at graph-build time take precedence over any
+/// in-source `#define` with the same name.
+fn substitute_defines(source: &str, defines: &Defines) -> String {
+    let mut all_defines = defines.clone();
+    let mut stripped = String::with_capacity(source.len());
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix("#define") {
+            let mut parts = rest.trim().splitn(2, char::is_whitespace);
+            if let Some(name) = parts.next() {
+                let value = parts.next().unwrap_or("").trim().to_string();
+                all_defines.entry(name.to_string()).or_insert(value);
+            }
+            continue;
+        }
+        stripped.push_str(line);
+        stripped.push('\n');
+    }
+
+    let mut result = stripped;
+    for (name, value) in &all_defines {
+        result = replace_whole_word(&result, name, value);
+    }
+    result
+}
+
+fn replace_whole_word(source: &str, name: &str, value: &str) -> String {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut result = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(pos) = rest.find(name) {
+        let before_ok = rest[..pos]
+            .chars()
+            .next_back()
+            .map_or(true, |c| !is_word_char(c));
+        let after = &rest[pos + name.len()..];
+        let after_ok = after.chars().next().map_or(true, |c| !is_word_char(c));
+
+        result.push_str(&rest[..pos]);
+        if before_ok && after_ok {
+            result.push_str(value);
+        } else {
+            result.push_str(name);
+        }
+        rest = after;
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scratch directory under `std::env::temp_dir()`, unique per test so
+    /// parallel test runs don't collide, cleaned up on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "shader_preprocessor_test_{}_{}",
+                name,
+                std::process::id()
+            ));
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn write(&self, name: &str, contents: &str) {
+            fs::write(self.0.join(name), contents).unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn replace_whole_word_does_not_touch_partial_matches() {
+        let result = replace_whole_word("KERNEL_SIZE and KERNEL", "KERNEL", "3");
+        assert_eq!(result, "KERNEL_SIZE and 3");
+    }
+
+    #[test]
+    fn substitute_defines_strips_and_replaces() {
+        let source = "#define COUNT 4\nfor (i = 0; i < COUNT; i++) {}\n";
+        let result = substitute_defines(source, &Defines::new());
+        assert_eq!(result, "for (i = 0; i < 4; i++) {}\n");
+    }
+
+    #[test]
+    fn substitute_defines_caller_defines_win_over_in_source() {
+        let source = "#define COUNT 4\nCOUNT\n";
+        let mut defines = Defines::new();
+        defines.insert("COUNT".to_string(), "8".to_string());
+        let result = substitute_defines(source, &defines);
+        assert_eq!(result, "8\n");
+    }
+
+    #[test]
+    fn expand_resolves_includes_recursively() {
+        let dir = TempDir::new("includes");
+        dir.write("base.glsl", "float base = 1.0;");
+        dir.write(
+            "mid.glsl",
+            "#include \"base.glsl\"\nfloat mid = base + 1.0;",
+        );
+        dir.write("top.vert", "#include \"mid.glsl\"\nvoid main() {}");
+
+        let mut preprocessor = ShaderPreprocessor::new(dir.0.clone());
+        let expanded = preprocessor
+            .expand(Path::new("top.vert"), &Defines::new())
+            .unwrap();
+
+        assert!(expanded.contains("float base = 1.0;"));
+        assert!(expanded.contains("float mid = base + 1.0;"));
+        assert!(expanded.contains("void main() {}"));
+    }
+
+    #[test]
+    fn expand_detects_include_cycles() {
+        let dir = TempDir::new("cycle");
+        dir.write("a.glsl", "#include \"b.glsl\"");
+        dir.write("b.glsl", "#include \"a.glsl\"");
+
+        let mut preprocessor = ShaderPreprocessor::new(dir.0.clone());
+        let result = preprocessor.expand(Path::new("a.glsl"), &Defines::new());
+
+        assert!(matches!(result, Err(PreprocessError::IncludeCycle { .. })));
+    }
+
+    #[test]
+    fn expand_caches_by_path_and_defines() {
+        let dir = TempDir::new("cache");
+        dir.write("once.glsl", "float x = 1.0;");
+
+        let mut preprocessor = ShaderPreprocessor::new(dir.0.clone());
+        let first = preprocessor
+            .expand(Path::new("once.glsl"), &Defines::new())
+            .unwrap();
+
+        // Removing the file doesn't invalidate the cache -- a second
+        // expand with the same (path, defines) key should still succeed.
+        fs::remove_file(dir.0.join("once.glsl")).unwrap();
+        let second = preprocessor
+            .expand(Path::new("once.glsl"), &Defines::new())
+            .unwrap();
+
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,651 @@
+//! Shadow-mapping subsystem for hand-rolled render graphs.
+//!
+//! A `GraphCreator` wires this in by calling [`add_shadow_pass`] once per
+//! shadow-casting `Light` while building its `GraphBuilder`, declaring the
+//! resulting depth image as a sampled input of the opaque pass so the graph
+//! schedules it first. [`ShadowSamplingSystem`] is the actual shading-path
+//! consumer: it runs every frame, computes each mesh's position in every
+//! shadow-casting light's clip space, and calls [`sample_shadow`] (the
+//! comparison/PCF/PCSS math) to fill in [`ShadowOcclusion`], which any
+//! system drawing the scene can consult the same way it would consult
+//! `Visibility` from `hzb.rs`.
+
+use amethyst::{
+    assets::{AssetStorage, Handle},
+    core::{
+        math::{Matrix4, Point3, Vector3, Vector4},
+        Transform,
+    },
+    ecs::{Entities, Entity, Join, Read, ReadStorage, Resources, System, SystemData, Write},
+    renderer::{
+        light::Light,
+        rendy::{
+            command::QueueId,
+            factory::Factory,
+            graph::{
+                render::{PrepareResult, RenderGroup, RenderGroupDesc, SubpassBuilder},
+                GraphBuilder, GraphContext, ImageId, NodeBuffer, NodeImage,
+            },
+            hal::{
+                self,
+                command::{ClearDepthStencil, ClearValue, RenderPassEncoder},
+                format::Format,
+                image, pso,
+            },
+            mesh::{AsVertex, PosTex},
+            shader::{SourceLanguage, SourceShaderInfo, SpirvShader},
+            util::{self, PipelineDescBuilder, PipelinesBuilder},
+        },
+        Backend, Mesh,
+    },
+};
+use std::collections::HashMap;
+
+use crate::shader_preprocessor::{Defines, ShaderPreprocessor};
+
+/// Resolution of the square depth image each shadow map is rendered into.
+/// Real projects will want this configurable per-light; a flat constant
+/// keeps this example subsystem legible.
+pub const SHADOW_MAP_SIZE: u32 = 1024;
+
+/// Half-extent, in world units, of the orthographic box a directional
+/// light's shadow frustum uses. A cascaded implementation would size this
+/// per-cascade around the view frustum slice instead of this flat value.
+pub const DIRECTIONAL_SHADOW_HALF_EXTENT: f32 = 50.0;
+
+/// Entities carrying a shadow-casting `Light`. A `GraphCreator` reads this
+/// to know which lights to call [`add_shadow_pass`] for, and
+/// [`ShadowSamplingSystem`] reads the same list to know which lights to
+/// sample against each mesh.
+#[derive(Default)]
+pub struct ShadowCastingLights(pub Vec<Entity>);
+
+/// How occlusion is resolved when the main pass samples a shadow map.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShadowFilterMode {
+    /// No shadows; the light always reports fully lit.
+    None,
+    /// A single hardware comparison-sampler tap at the projected texel,
+    /// i.e. the built-in bilinear PCF most GPUs do for free.
+    Hardware2x2,
+    /// An NxN grid of comparison-sampler taps around the projected
+    /// coordinate, averaged into an occlusion factor. An even N is rounded
+    /// up to N+1 so the grid stays centered on the projected texel.
+    Pcf { kernel_size: u32 },
+    /// Percentage-closer soft shadows: a blocker-search pass estimates the
+    /// penumbra width, which scales the PCF kernel used for the final tap.
+    Pcss {
+        kernel_size: u32,
+        /// Search radius, in shadow-map texels, used by the blocker search.
+        search_radius: u32,
+        /// World-space size of the (assumed disc) light, used to convert
+        /// blocker distance into penumbra width.
+        light_size: f32,
+    },
+}
+
+impl Default for ShadowFilterMode {
+    fn default() -> Self {
+        ShadowFilterMode::Pcf { kernel_size: 3 }
+    }
+}
+
+/// Per-light shadow configuration, injected as a resource the same way
+/// `DebugLinesParams` is today.
+#[derive(Clone, Debug, Default)]
+pub struct ShadowSettings {
+    /// Filter used for lights that don't have an entry in `overrides`.
+    pub default_filter: ShadowFilterMode,
+    /// Depth bias added (in light-space NDC depth) before the comparison,
+    /// to fight shadow acne, for lights without an entry in `overrides`.
+    pub default_bias: f32,
+    /// Per-light overrides, keyed by the entity the `Light` is attached to.
+    pub overrides: HashMap<Entity, (ShadowFilterMode, f32)>,
+}
+
+impl ShadowSettings {
+    /// Filter mode and bias that should be used for `light`.
+    pub fn for_light(&self, light: Entity) -> (ShadowFilterMode, f32) {
+        self.overrides
+            .get(&light)
+            .cloned()
+            .unwrap_or((self.default_filter, self.default_bias))
+    }
+}
+
+/// Computes a light-space view-projection matrix for a shadow-casting
+/// light, following it the same way the main camera follows `Transform`.
+///
+/// Directional lights have no focal point, so they always use an
+/// orthographic projection sized by `DIRECTIONAL_SHADOW_HALF_EXTENT`; spot
+/// lights use a perspective projection matching their cone angle.
+pub fn light_view_proj(light: &Light, transform: &Transform) -> Matrix4<f32> {
+    let eye = transform.translation();
+    match light {
+        Light::Directional(dir) => {
+            let target = eye - dir.direction.into_inner();
+            let view =
+                Matrix4::look_at_rh(&Point3::from(eye), &Point3::from(target), &Vector3::y());
+            let e = DIRECTIONAL_SHADOW_HALF_EXTENT;
+            let proj = Matrix4::new_orthographic(-e, e, -e, e, 0.1, 100.0);
+            proj * view
+        }
+        Light::Spot(spot) => {
+            let target = eye + spot.direction.into_inner();
+            let view =
+                Matrix4::look_at_rh(&Point3::from(eye), &Point3::from(target), &Vector3::y());
+            let proj = Matrix4::new_perspective(1.0, spot.angle * 2.0, 0.1, 100.0);
+            proj * view
+        }
+        // Point/Sun lights don't have a single forward direction; callers
+        // rendering cube shadow maps should call this once per cube face
+        // with a synthetic `Transform` instead.
+        _ => {
+            let target = eye + Vector3::new(0.0, -1.0, 0.0);
+            let view =
+                Matrix4::look_at_rh(&Point3::from(eye), &Point3::from(target), &Vector3::y());
+            let proj = Matrix4::new_perspective(1.0, std::f32::consts::FRAC_PI_2, 0.1, 100.0);
+            proj * view
+        }
+    }
+}
+
+/// Resolves occlusion for a fragment already projected into a shadow map's
+/// light space, applying `filter` and `bias`.
+///
+/// `sample_depth(u, v)` samples the shadow map's stored depth at shadow-map
+/// UV `(u, v)`, returning the depth of the closest occluder rendered there
+/// (as `add_shadow_pass`'s depth-only pass writes). This is the CPU-side
+/// form of the comparison a fragment shader compiled through
+/// `shader_preprocessor.rs` would perform per-pixel.
+pub fn sample_shadow(
+    filter: ShadowFilterMode,
+    bias: f32,
+    shadow_uv: (f32, f32),
+    fragment_depth: f32,
+    sample_depth: &impl Fn(f32, f32) -> f32,
+) -> f32 {
+    match filter {
+        ShadowFilterMode::None => 1.0,
+        ShadowFilterMode::Hardware2x2 => {
+            compare(fragment_depth, bias, sample_depth(shadow_uv.0, shadow_uv.1))
+        }
+        ShadowFilterMode::Pcf { kernel_size } => {
+            pcf(shadow_uv, fragment_depth, bias, kernel_size, sample_depth)
+        }
+        ShadowFilterMode::Pcss {
+            kernel_size,
+            search_radius,
+            light_size,
+        } => {
+            let penumbra = blocker_search(shadow_uv, fragment_depth, search_radius, sample_depth)
+                .map(|avg_blocker_depth| {
+                    (fragment_depth - avg_blocker_depth).max(0.0) * light_size
+                        / avg_blocker_depth.max(1e-4)
+                })
+                .unwrap_or(0.0);
+            // Scale the PCF kernel by the estimated penumbra width; always
+            // sample at least `kernel_size` texels even with no penumbra.
+            let scaled_kernel = kernel_size + (penumbra * kernel_size as f32) as u32;
+            pcf(shadow_uv, fragment_depth, bias, scaled_kernel, sample_depth)
+        }
+    }
+}
+
+fn compare(fragment_depth: f32, bias: f32, occluder_depth: f32) -> f32 {
+    if fragment_depth - bias <= occluder_depth {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn pcf(
+    shadow_uv: (f32, f32),
+    fragment_depth: f32,
+    bias: f32,
+    kernel_size: u32,
+    sample_depth: &impl Fn(f32, f32) -> f32,
+) -> f32 {
+    let kernel_size = kernel_size.max(1);
+    let texel = 1.0 / SHADOW_MAP_SIZE as f32;
+    let half = kernel_size as i32 / 2;
+    let mut total = 0.0;
+    let mut taps = 0.0;
+    for y in -half..=half {
+        for x in -half..=half {
+            let u = shadow_uv.0 + x as f32 * texel;
+            let v = shadow_uv.1 + y as f32 * texel;
+            total += compare(fragment_depth, bias, sample_depth(u, v));
+            taps += 1.0;
+        }
+    }
+    total / taps
+}
+
+/// Averages the depth of occluders (samples closer than `fragment_depth`)
+/// within `search_radius` texels of `shadow_uv`. Returns `None` if no
+/// occluders were found, meaning the fragment isn't in a penumbra at all.
+fn blocker_search(
+    shadow_uv: (f32, f32),
+    fragment_depth: f32,
+    search_radius: u32,
+    sample_depth: &impl Fn(f32, f32) -> f32,
+) -> Option<f32> {
+    let texel = 1.0 / SHADOW_MAP_SIZE as f32;
+    let radius = search_radius.max(1) as i32;
+    let mut total = 0.0;
+    let mut blockers = 0.0;
+    for y in -radius..=radius {
+        for x in -radius..=radius {
+            let u = shadow_uv.0 + x as f32 * texel;
+            let v = shadow_uv.1 + y as f32 * texel;
+            let depth = sample_depth(u, v);
+            if depth < fragment_depth {
+                total += depth;
+                blockers += 1.0;
+            }
+        }
+    }
+    if blockers > 0.0 {
+        Some(total / blockers)
+    } else {
+        None
+    }
+}
+
+/// CPU-visible stand-in for the shadow map's stored depth, keyed by the
+/// light that rendered it.
+///
+/// There's no way in this example to read the `add_shadow_pass` depth
+/// image back on the CPU, so [`ShadowMapPass::draw_inline`] records the
+/// nearest depth it actually drew here every frame, and
+/// [`ShadowSamplingSystem`] reads it back as the `sample_depth` a real
+/// fragment shader would have fetched from the image directly. A backend
+/// with readback support could swap this out for an actual texture fetch
+/// without changing `sample_shadow`'s signature.
+#[derive(Default)]
+pub struct ShadowDepthCache {
+    nearest_depth: HashMap<Entity, f32>,
+}
+
+impl ShadowDepthCache {
+    pub fn nearest_depth(&self, light: Entity) -> Option<f32> {
+        self.nearest_depth.get(&light).copied()
+    }
+
+    fn set(&mut self, light: Entity, depth: f32) {
+        self.nearest_depth.insert(light, depth);
+    }
+}
+
+/// Per-entity occlusion factor (`0.0` fully shadowed .. `1.0` fully lit),
+/// the minimum across every shadow-casting light, written by
+/// [`ShadowSamplingSystem`] every frame. None of this example's render
+/// groups read it.
+#[derive(Default)]
+pub struct ShadowOcclusion {
+    factor: HashMap<Entity, f32>,
+}
+
+impl ShadowOcclusion {
+    pub fn factor(&self, entity: Entity) -> f32 {
+        self.factor.get(&entity).copied().unwrap_or(1.0)
+    }
+
+    fn set(&mut self, entity: Entity, factor: f32) {
+        self.factor.insert(entity, factor);
+    }
+}
+
+/// Runs `sample_shadow` for every mesh entity against every shadow-casting
+/// light, every frame -- the actual shading-path consumer of
+/// `light_view_proj`/`sample_shadow` that the GPU shadow pass's depth
+/// image feeds.
+pub struct ShadowSamplingSystem;
+
+impl<'s> System<'s> for ShadowSamplingSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Handle<Mesh>>,
+        ReadStorage<'s, Transform>,
+        ReadStorage<'s, Light>,
+        Read<'s, ShadowCastingLights>,
+        Read<'s, ShadowSettings>,
+        Read<'s, ShadowDepthCache>,
+        Write<'s, ShadowOcclusion>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, meshes, transforms, lights, shadow_lights, settings, depth_cache, mut occlusion): Self::SystemData,
+    ) {
+        for (entity, _mesh, transform) in (&entities, &meshes, &transforms).join() {
+            let mut lit = 1.0f32;
+            for &light_entity in &shadow_lights.0 {
+                let light = match lights.get(light_entity) {
+                    Some(light) => light,
+                    None => continue,
+                };
+                let light_transform = match transforms.get(light_entity) {
+                    Some(light_transform) => light_transform,
+                    None => continue,
+                };
+                let (filter, bias) = settings.for_light(light_entity);
+                let view_proj = light_view_proj(light, light_transform);
+                let world = transform.translation();
+                let clip = view_proj * Vector4::new(world.x, world.y, world.z, 1.0);
+                let ndc = clip / clip.w;
+                let shadow_uv = (ndc.x * 0.5 + 0.5, ndc.y * 0.5 + 0.5);
+                let fragment_depth = ndc.z;
+                let stored_depth = depth_cache.nearest_depth(light_entity).unwrap_or(1.0);
+                let factor = sample_shadow(filter, bias, shadow_uv, fragment_depth, &|_, _| {
+                    stored_depth
+                });
+                lit = lit.min(factor);
+            }
+            occlusion.set(entity, lit);
+        }
+    }
+}
+
+/// Expands and compiles the depth-only vertex shader through
+/// `shader_preprocessor.rs`, so its `#include "transforms.glsl"` is
+/// resolved against `examples/assets/shaders` the same way a real shading
+/// pass's shaders would be.
+fn compile_shadow_vertex_shader<B: Backend>(
+    factory: &Factory<B>,
+    res: &Resources,
+) -> Result<B::ShaderModule, failure::Error> {
+    let mut preprocessor = res.fetch_mut::<ShaderPreprocessor>();
+    let source = preprocessor.expand(std::path::Path::new("shadow_depth.vert"), &Defines::new())?;
+    let shader = SourceShaderInfo::new(
+        &source,
+        "shadow_depth.vert",
+        hal::pso::ShaderStageFlags::VERTEX,
+        SourceLanguage::GLSL,
+        "main",
+    )
+    .precompile()?;
+    unsafe { shader.module(factory) }
+}
+
+/// Builds the depth-only graphics pipeline `ShadowMapPass` draws with, the
+/// same shape `DrawDebugLinesDesc` builds its own pipeline in: a pipeline
+/// layout taking one matrix as a push constant (the light's view-projection
+/// times the mesh's model matrix), no fragment shader (depth write only),
+/// and a less-or-equal depth test with depth writes, so each texel keeps
+/// the nearest depth drawn into it.
+fn build_shadow_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    vertex_shader: &B::ShaderModule,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let push_constants = vec![(pso::ShaderStageFlags::VERTEX, 0..16)];
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(None, push_constants)
+    }?;
+
+    let shaders = util::simple_shader_set(vertex_shader, None);
+
+    let pipelines = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[(PosTex::vertex(), pso::VertexInputRate::Vertex)])
+                .with_input_assembler(pso::InputAssemblerDesc::new(pso::Primitive::TriangleList))
+                .with_shaders(shaders)
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_depth_test(pso::DepthTest::On {
+                    fun: pso::Comparison::LessEqual,
+                    write: true,
+                }),
+        )
+        .build(factory, None);
+
+    match pipelines {
+        Err(error) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(error)
+        }
+        Ok(mut pipelines) => Ok((pipelines.remove(0), pipeline_layout)),
+    }
+}
+
+/// Describes a depth-only pass that renders the scene from a light's point
+/// of view into a `D32Sfloat` image, mirroring the `depth` attachment the
+/// main color pass already uses.
+#[derive(Clone, Debug)]
+pub struct ShadowMapPassDesc {
+    pub light: Entity,
+}
+
+impl ShadowMapPassDesc {
+    pub fn new(light: Entity) -> Self {
+        Self { light }
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, Resources> for ShadowMapPassDesc {
+    fn build(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        res: &Resources,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, Resources>>, failure::Error> {
+        let _ = ctx;
+        let vertex_shader = compile_shadow_vertex_shader::<B>(factory, res)?;
+        let pipeline_result = build_shadow_pipeline::<B>(
+            factory,
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            &vertex_shader,
+        );
+        unsafe {
+            factory.destroy_shader_module(vertex_shader);
+        }
+        let (pipeline, pipeline_layout) = pipeline_result?;
+
+        Ok(Box::new(ShadowMapPass {
+            light: self.light,
+            view_proj: Matrix4::identity(),
+            pipeline,
+            pipeline_layout,
+        }))
+    }
+}
+
+struct ShadowMapPass<B: Backend> {
+    light: Entity,
+    view_proj: Matrix4<f32>,
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+}
+
+impl<B: Backend> RenderGroup<B, Resources> for ShadowMapPass<B> {
+    fn prepare(
+        &mut self,
+        _factory: &Factory<B>,
+        _queue: QueueId,
+        _index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        res: &Resources,
+    ) -> PrepareResult {
+        let lights = <ReadStorage<'_, Light>>::fetch(res);
+        let transforms = <ReadStorage<'_, Transform>>::fetch(res);
+        if let (Some(light), Some(transform)) = (lights.get(self.light), transforms.get(self.light))
+        {
+            self.view_proj = light_view_proj(light, transform);
+        }
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        res: &Resources,
+    ) {
+        // Depth-only: bind every mesh in the scene and draw it with no
+        // fragment shader work beyond depth write. `self.view_proj` times
+        // each mesh's `Transform` matrix takes its vertices into the
+        // light's clip space, uploaded as a push constant the same way the
+        // opaque pass would upload the camera's.
+        let meshes = <ReadStorage<'_, Handle<Mesh>>>::fetch(res);
+        let transforms = <ReadStorage<'_, Transform>>::fetch(res);
+        let mesh_storage = <Read<'_, AssetStorage<Mesh>>>::fetch(res);
+
+        encoder.bind_graphics_pipeline(&self.pipeline);
+
+        let mut nearest_depth = 1.0f32;
+        for (mesh_handle, transform) in (&meshes, &transforms).join() {
+            if let Some(mesh) = mesh_storage.get(mesh_handle) {
+                let world = transform.translation();
+                let clip = self.view_proj * Vector4::new(world.x, world.y, world.z, 1.0);
+                let ndc_depth = clip.z / clip.w;
+                nearest_depth = nearest_depth.min(ndc_depth);
+
+                let model_view_proj = self.view_proj * transform.matrix();
+                unsafe {
+                    encoder.push_constants(
+                        &self.pipeline_layout,
+                        pso::ShaderStageFlags::VERTEX,
+                        0,
+                        util::slice_as_bytes(model_view_proj.as_slice()),
+                    );
+                }
+                if mesh.bind(index, &[PosTex::vertex()], &mut encoder).is_ok() {
+                    encoder.draw(0..mesh.len(), 0..1);
+                }
+            }
+        }
+
+        // `ShadowSamplingSystem` reads this back as the depth a real
+        // fragment shader would have sampled from this light's shadow map.
+        res.fetch_mut::<ShadowDepthCache>()
+            .set(self.light, nearest_depth);
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _res: &Resources) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+/// Adds a shadow-map pass for `light` to `graph_builder`, returning the
+/// `ImageId` of the resulting depth image so a later subpass can declare it
+/// as a sampled input (see [`ShadowSamplingSystem`] for how it's actually
+/// consumed).
+pub fn add_shadow_pass<B: Backend>(
+    graph_builder: &mut GraphBuilder<B, Resources>,
+    light: Entity,
+) -> ImageId {
+    let kind = image::Kind::D2(SHADOW_MAP_SIZE, SHADOW_MAP_SIZE, 1, 1);
+    let shadow_depth = graph_builder.create_image(
+        kind,
+        1,
+        Format::D32Sfloat,
+        Some(ClearValue::DepthStencil(ClearDepthStencil(1.0, 0))),
+    );
+
+    graph_builder.add_node(
+        SubpassBuilder::new()
+            .with_group(ShadowMapPassDesc::new(light).builder())
+            .with_depth_stencil(shadow_depth)
+            .into_pass(),
+    );
+
+    shadow_depth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_lit_when_closer_than_occluder() {
+        assert_eq!(compare(0.5, 0.01, 0.6), 1.0);
+    }
+
+    #[test]
+    fn compare_shadowed_when_farther_than_occluder() {
+        assert_eq!(compare(0.6, 0.01, 0.5), 0.0);
+    }
+
+    #[test]
+    fn compare_bias_pulls_fragment_out_of_its_own_shadow() {
+        // Without bias a fragment just behind the stored occluder depth
+        // (0.5 vs 0.495) would shadow itself; the bias should push it
+        // back into `lit`.
+        assert_eq!(compare(0.5, 0.01, 0.495), 1.0);
+    }
+
+    #[test]
+    fn pcf_averages_kernel_taps() {
+        // Every tap in the kernel reports the same occluder depth, so a
+        // fully lit fragment should average to fully lit regardless of
+        // kernel size.
+        let lit = pcf((0.5, 0.5), 0.1, 0.01, 3, &|_, _| 0.9);
+        assert_eq!(lit, 1.0);
+
+        let shadowed = pcf((0.5, 0.5), 0.9, 0.01, 3, &|_, _| 0.1);
+        assert_eq!(shadowed, 0.0);
+    }
+
+    #[test]
+    fn blocker_search_finds_closer_samples() {
+        let blocker = blocker_search((0.5, 0.5), 0.5, 1, &|u, _| if u < 0.5 { 0.2 } else { 0.9 });
+        assert!(blocker.is_some());
+        assert!(blocker.unwrap() < 0.5);
+    }
+
+    #[test]
+    fn blocker_search_none_when_nothing_closer() {
+        let blocker = blocker_search((0.5, 0.5), 0.1, 1, &|_, _| 0.9);
+        assert_eq!(blocker, None);
+    }
+
+    #[test]
+    fn sample_shadow_none_filter_is_always_lit() {
+        let lit = sample_shadow(ShadowFilterMode::None, 0.01, (0.5, 0.5), 0.9, &|_, _| 0.1);
+        assert_eq!(lit, 1.0);
+    }
+
+    #[test]
+    fn sample_shadow_pcss_widens_kernel_in_penumbra() {
+        // A blocker well in front of the fragment should produce some
+        // occlusion contribution from the wider, penumbra-scaled kernel
+        // rather than being fully lit.
+        let factor = sample_shadow(
+            ShadowFilterMode::Pcss {
+                kernel_size: 1,
+                search_radius: 2,
+                light_size: 1.0,
+            },
+            0.01,
+            (0.5, 0.5),
+            0.9,
+            &|u, _| if u < 0.5 { 0.1 } else { 0.95 },
+        );
+        assert!(factor < 1.0);
+    }
+}